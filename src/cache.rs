@@ -0,0 +1,146 @@
+//! Expressions which memoize their result, for cheap re-use of sub-expressions.
+
+use std::borrow::Borrow;
+use std::cell::{Cell, UnsafeCell};
+use std::ops::Deref;
+
+use super::{EvalError, Expression};
+use super::describe::ExprNode;
+
+/// An expression which evaluates its inner expression at most once.
+///
+/// Wraps an expression so that the first call to [`force`](Cached::force) (or
+/// any of the reference-access impls built on top of it) performs the
+/// computation and stores the result; every subsequent access hands back the
+/// same cached value instead of recomputing it. This lets a single
+/// sub-expression be shared between several consumers (e.g. `magnitude`
+/// feeding two different expressions) without cloning the whole tree.
+pub struct Cached<T, E: Expression<T>> {
+    expr: Cell<Option<E>>,
+    value: UnsafeCell<Option<T>>,
+}
+
+impl<T, E: Expression<T>> Cached<T, E> {
+    /// Wraps `expr` so its result will be computed at most once.
+    pub fn new(expr: E) -> Self {
+        Cached {
+            expr: Cell::new(Some(expr)),
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    /// Evaluates the inner expression if it hasn't been already, and returns
+    /// a reference to the (now guaranteed present) cached value.
+    ///
+    /// # Panics
+    /// If called re-entrantly while the inner expression is being evaluated
+    /// (i.e. the expression's own evaluation tries to force this same
+    /// `Cached` again), since that would otherwise observe a half-initialized
+    /// cell.
+    pub fn force(&self) -> &T {
+        // Safety: `value` is only ever written once, right here, and only
+        // after `expr` has been taken out of its cell so a re-entrant call
+        // can't reach this branch twice concurrently (it would instead hit
+        // the `expect` below).
+        if unsafe { (*self.value.get()).is_none() } {
+            let expr = self.expr.take()
+                .expect("Cached expression re-entrantly forced during its own evaluation");
+            let value = expr.evaluate();
+            unsafe {
+                *self.value.get() = Some(value);
+            }
+        }
+
+        unsafe { (*self.value.get()).as_ref().unwrap() }
+    }
+}
+
+impl<T, E: Expression<T>> Expression<T> for Cached<T, E> {
+    fn evaluate(self) -> T {
+        match self.expr.into_inner() {
+            Some(expr) => expr.evaluate(),
+            None => self.value.into_inner().expect("Cached value missing despite being forced"),
+        }
+    }
+
+    fn try_evaluate(self) -> Result<T, EvalError> {
+        match self.expr.into_inner() {
+            Some(expr) => expr.try_evaluate(),
+            None => Ok(self.value.into_inner().expect("Cached value missing despite being forced")),
+        }
+    }
+
+    fn describe(&self) -> ExprNode {
+        // Peek without consuming: take the inner expression out just long
+        // enough to describe it, then put it straight back.
+        let taken = self.expr.take();
+        let node = match &taken {
+            Some(expr) => ExprNode::Cached(Box::new(expr.describe())),
+            // Already forced: the expression is gone, but the value it
+            // produced is now effectively a constant.
+            None => ExprNode::Cached(Box::new(ExprNode::Value)),
+        };
+        self.expr.set(taken);
+
+        node
+    }
+}
+
+impl<T, E: Expression<T>> Deref for Cached<T, E> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.force()
+    }
+}
+
+impl<T, E: Expression<T>> AsRef<T> for Cached<T, E> {
+    fn as_ref(&self) -> &T {
+        self.force()
+    }
+}
+
+impl<T, E: Expression<T>> Borrow<T> for Cached<T, E> {
+    fn borrow(&self) -> &T {
+        self.force()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell as StdCell;
+
+    #[test]
+    fn caches_result() {
+        let calls = StdCell::new(0u32);
+        let cached = super::super::lazyf(|| {
+            calls.set(calls.get() + 1);
+            42
+        }).cache();
+
+        assert_eq!(*cached, 42);
+        assert_eq!(*cached, 42);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn evaluate_consumes_without_forcing() {
+        let cached = Cached::new(super::super::lazy(7));
+        assert_eq!(cached.evaluate(), 7);
+    }
+
+    #[test]
+    fn evaluate_after_force_returns_cached_value() {
+        let cached = Cached::new(super::super::lazy(7));
+        assert_eq!(*cached.force(), 7);
+        assert_eq!(cached.evaluate(), 7);
+    }
+
+    #[test]
+    fn describe_does_not_consume() {
+        let cached = Cached::new(super::super::lazy(7));
+        assert_eq!(cached.describe(), ExprNode::Cached(Box::new(ExprNode::Value)));
+        assert_eq!(cached.evaluate(), 7);
+    }
+}
@@ -0,0 +1,97 @@
+//! Introspection of expression trees.
+//!
+//! Every combinator produces its own distinct generic type (`LazyMap`, the
+//! `ops` types, `Parameter`, `Value`, ...), so there's no way to inspect what
+//! an expression *is* from the outside before evaluating it. `Expression::describe`
+//! returns a borrowed, non-consuming tree mirroring the expression's
+//! structure - useful for debugging, cache keys, or displaying a formula to
+//! a user.
+
+use std::fmt;
+
+/// A node in the tree returned by `Expression::describe`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprNode {
+    /// A known, unchanging value.
+    Value,
+    /// The result of an argument-less function.
+    Function,
+    /// A `Parameter` (or `SyncParameter`).
+    Parameter {
+        /// Whether the parameter currently holds a value.
+        filled: bool,
+    },
+    /// The result of `Expression::map`.
+    Map(Box<ExprNode>),
+    /// The result of one of the `ops` arithmetic operators.
+    BinOp {
+        op: OpKind,
+        lhs: Box<ExprNode>,
+        rhs: Box<ExprNode>,
+    },
+    /// The result of `Expression::and_then`/`flat_map`.
+    AndThen(Box<ExprNode>),
+    /// The result of `Expression::zip`.
+    Zip(Box<ExprNode>, Box<ExprNode>),
+    /// The result of `Expression::flatten`.
+    Flatten(Box<ExprNode>),
+    /// The result of `Expression::cache`.
+    Cached(Box<ExprNode>),
+}
+
+impl fmt::Display for ExprNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprNode::Value => write!(f, "<value>"),
+            ExprNode::Function => write!(f, "<function>"),
+            ExprNode::Parameter { filled } => {
+                write!(f, "<parameter>")?;
+                if !filled {
+                    write!(f, "?")?;
+                }
+                Ok(())
+            }
+            ExprNode::Map(inner) => write!(f, "map({})", inner),
+            ExprNode::BinOp { op, lhs, rhs } => write!(f, "({} {} {})", lhs, op, rhs),
+            ExprNode::AndThen(inner) => write!(f, "and_then({})", inner),
+            ExprNode::Zip(lhs, rhs) => write!(f, "zip({}, {})", lhs, rhs),
+            ExprNode::Flatten(inner) => write!(f, "flatten({})", inner),
+            ExprNode::Cached(inner) => write!(f, "cached({})", inner),
+        }
+    }
+}
+
+/// The arithmetic operator behind a `BinOp` node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpKind {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl fmt::Display for OpKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            OpKind::Add => "+",
+            OpKind::Sub => "-",
+            OpKind::Mul => "*",
+            OpKind::Div => "/",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_bin_op() {
+        let node = ExprNode::BinOp {
+            op: OpKind::Add,
+            lhs: Box::new(ExprNode::Parameter { filled: true }),
+            rhs: Box::new(ExprNode::Parameter { filled: false }),
+        };
+        assert_eq!(node.to_string(), "(<parameter> + <parameter>?)");
+    }
+}
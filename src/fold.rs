@@ -0,0 +1,47 @@
+//! Constant-folding optimization over expression trees.
+//!
+//! [`Expression::optimize`] checks whether a whole expression is made up only
+//! of `Value`, `Function`, `map` and the `ops` operators, with no `Parameter`
+//! anywhere in it; if so, it evaluates the expression eagerly and replaces it
+//! with its precomputed value. Otherwise the expression is returned
+//! unchanged: constant subtrees inside an expression that also depends on a
+//! `Parameter` are not folded, and `and_then`, `zip`, `flatten` and `cache`
+//! nodes are never folded.
+
+use super::{EvalError, Expression};
+use super::describe::ExprNode;
+
+/// The result of [`Expression::fold`]: either the expression turned out to
+/// be entirely constant, or it could not be folded (it depends on a
+/// `Parameter` or contains a node that doesn't fold) and is kept around
+/// unchanged.
+///
+/// Implements `Expression<T>` itself, so it can be evaluated (or further
+/// composed) exactly like the expression it replaces.
+pub enum Folded<T, E: Expression<T>> {
+    Const(T),
+    Dynamic(E),
+}
+
+impl<T, E: Expression<T>> Expression<T> for Folded<T, E> {
+    fn evaluate(self) -> T {
+        match self {
+            Folded::Const(value) => value,
+            Folded::Dynamic(expr) => expr.evaluate(),
+        }
+    }
+
+    fn try_evaluate(self) -> Result<T, EvalError> {
+        match self {
+            Folded::Const(value) => Ok(value),
+            Folded::Dynamic(expr) => expr.try_evaluate(),
+        }
+    }
+
+    fn describe(&self) -> ExprNode {
+        match self {
+            Folded::Const(_) => ExprNode::Value,
+            Folded::Dynamic(expr) => expr.describe(),
+        }
+    }
+}
@@ -16,4 +16,13 @@ fn complex_expression() {
     xs.set(5.0);
     ys.set(3.0);
     assert_eq!(magnitude2.evaluate(), 5.8309518948453004708741528775456);
+}
+
+#[test]
+fn complex_expression_description() {
+    let (x, _) = Parameter::<f32>::empty();
+    let (y, _) = Parameter::<f32>::empty();
+    let magnitude = (x.map(pow2) + y.map(pow2)).map(f32::sqrt);
+
+    assert_eq!(magnitude.describe().to_string(), "map((map(<parameter>?) + map(<parameter>?)))");
 }
\ No newline at end of file
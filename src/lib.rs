@@ -4,17 +4,51 @@
 //! which are representations of a future computation. It is
 //! entirely implemented with generic types, no macros involved.
 
+use std::fmt;
 use std::marker::PhantomData;
 
+pub mod cache;
+pub mod describe;
+pub mod fold;
 pub mod ops;
 pub mod param;
+pub mod sync;
+
+#[cfg(test)]
+mod integration_tests;
+
+use self::cache::Cached;
+use self::describe::ExprNode;
+use self::fold::Folded;
 
 /// Re-exports all necessary types for common usage
 pub mod prelude {
-    pub use super::{lazy, lazyf, Lazy, Expression};
+    pub use super::{lazy, lazyf, lazy_cached, EvalError, Lazy, Expression};
+    pub use super::cache::Cached;
+    pub use super::describe::ExprNode;
+    pub use super::fold::Folded;
     pub use super::param::{Parameter, ParameterContent};
 }
 
+/// Errors which can occur while evaluating an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// A `Parameter` was evaluated while holding no value (it was never
+    /// given one, or an earlier evaluation already took it).
+    MissingParameter,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::MissingParameter =>
+                write!(f, "parameter value not provided"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
 /// Convenience method to create a `Value` expression
 pub fn lazy<T>(value: T) -> Lazy<T, Value<T>> {
     Lazy::new(Value(value))
@@ -25,6 +59,13 @@ pub fn lazyf<T, F: FnOnce() -> T>(f: F) -> Lazy<T, Function<T, F>> {
     Lazy::new(Function(f))
 }
 
+/// Convenience method to create a memoizing `Cached` expression
+///
+/// See [`Expression::cache`] for details.
+pub fn lazy_cached<T, E: Expression<T>>(expr: E) -> Lazy<T, Cached<T, E>> {
+    Lazy::new(Cached::new(expr))
+}
+
 /// Wrapper type which delegates operators into expressions
 #[derive(Debug, Clone)]
 pub struct Lazy<T, E: Expression<T>>(E, PhantomData<T>);
@@ -40,6 +81,25 @@ impl<T, E: Expression<T>> Expression<T> for Lazy<T, E> {
     fn evaluate(self) -> T {
         self.0.evaluate()
     }
+
+    fn try_evaluate(self) -> Result<T, EvalError> {
+        self.0.try_evaluate()
+    }
+
+    fn describe(&self) -> ExprNode {
+        self.0.describe()
+    }
+
+    fn can_fold(&self) -> bool {
+        self.0.can_fold()
+    }
+
+    fn fold(self) -> Folded<T, Self> {
+        match self.0.fold() {
+            Folded::Const(value) => Folded::Const(value),
+            Folded::Dynamic(expr) => Folded::Dynamic(Lazy::new(expr)),
+        }
+    }
 }
 
 /// A known, unchanging, value expression
@@ -50,6 +110,18 @@ impl<T> Expression<T> for Value<T> {
     fn evaluate(self) -> T {
         self.0
     }
+
+    fn describe(&self) -> ExprNode {
+        ExprNode::Value
+    }
+
+    fn can_fold(&self) -> bool {
+        true
+    }
+
+    fn fold(self) -> Folded<T, Self> {
+        Folded::Const(self.0)
+    }
 }
 
 /// Wrapper for an argument-less function
@@ -61,6 +133,18 @@ impl<T, F: FnOnce() -> T> Expression<T> for Function<T, F> {
     fn evaluate(self) -> T {
         self.0()
     }
+
+    fn describe(&self) -> ExprNode {
+        ExprNode::Function
+    }
+
+    fn can_fold(&self) -> bool {
+        true
+    }
+
+    fn fold(self) -> Folded<T, Self> {
+        Folded::Const(self.evaluate())
+    }
 }
 
 /// Represents a future computation
@@ -75,6 +159,61 @@ pub trait Expression<T> {
     /// returning their value.
     fn evaluate(self) -> T;
 
+    /// Executes the expression, without panicking on unset parameters.
+    ///
+    /// Behaves like `evaluate`, but surfaces a missing `Parameter` value
+    /// as an `Err(EvalError)` instead of panicking. The default
+    /// implementation is for expressions that can't fail, and simply
+    /// defers to `evaluate`.
+    fn try_evaluate(self) -> Result<T, EvalError>
+        where Self: Sized
+    {
+        Ok(self.evaluate())
+    }
+
+    /// Describes the structure of the expression, without consuming it.
+    ///
+    /// Returns a tree mirroring how the expression was composed, useful for
+    /// debugging, cache keys, or displaying a formula to a user before it's
+    /// evaluated. See `describe::ExprNode`.
+    fn describe(&self) -> ExprNode;
+
+    /// Reports whether this expression is eligible for constant folding,
+    /// i.e. contains no `Parameter` anywhere in it.
+    ///
+    /// Used by `fold` to decide, without evaluating anything, whether a
+    /// subtree can be collapsed into a single value. The default is `false`,
+    /// the always-safe answer for expressions that don't override it.
+    fn can_fold(&self) -> bool {
+        false
+    }
+
+    /// Constant-folds the expression.
+    ///
+    /// Returns `Folded::Const` if this expression (and everything under it)
+    /// is free of `Parameter`s, evaluating it eagerly to get there; returns
+    /// `Folded::Dynamic` with the expression untouched otherwise. Must never
+    /// evaluate a `Parameter` to decide this - see `can_fold`. The default
+    /// implementation is the always-safe choice of leaving the expression
+    /// untouched.
+    fn fold(self) -> Folded<T, Self>
+        where Self: Sized
+    {
+        Folded::Dynamic(self)
+    }
+
+    /// Optimizes the expression by constant-folding it.
+    ///
+    /// A thin, public wrapper around `fold`: the returned `Folded` is itself
+    /// an expression. Only an expression that is foldable as a whole becomes
+    /// a precomputed value; any other expression is kept unchanged, including
+    /// its constant subtrees.
+    fn optimize(self) -> Folded<T, Self>
+        where Self: Sized
+    {
+        self.fold()
+    }
+
     /// Transform the value of an expression.
     ///
     /// Analogous to `Iterator::map` Creates an expression which transforms a value and assumes
@@ -84,6 +223,58 @@ pub trait Expression<T> {
     {
         Lazy::new(LazyMap(self, f, PhantomData, PhantomData))
     }
+
+    /// Wraps the expression so it evaluates at most once.
+    ///
+    /// The returned `Cached` caches its result after the first evaluation
+    /// and, unlike most other combinators, exposes it by reference (through
+    /// `Deref`, `AsRef` and `Borrow`) so the same computed value can be
+    /// shared between multiple consumers instead of recomputing it for each.
+    fn cache(self) -> Cached<T, Self>
+        where Self: Sized
+    {
+        Cached::new(self)
+    }
+
+    /// Builds a dependent expression from the value of this one.
+    ///
+    /// Analogous to `Result::and_then`. Unlike `map`, the provided function
+    /// returns a whole new expression (built using the produced value),
+    /// which is then evaluated in turn - useful when the shape of the rest
+    /// of the computation depends on a value only known at evaluation time.
+    fn and_then<U, E2: Expression<U>, F: FnOnce(T) -> E2>(self, f: F) -> Lazy<U, LazyAndThen<T, Self, U, E2, F>>
+        where Self: Sized
+    {
+        Lazy::new(LazyAndThen(self, f, PhantomData, PhantomData, PhantomData))
+    }
+
+    /// Alias for `and_then`, matching `Iterator::flat_map`'s naming.
+    fn flat_map<U, E2: Expression<U>, F: FnOnce(T) -> E2>(self, f: F) -> Lazy<U, LazyAndThen<T, Self, U, E2, F>>
+        where Self: Sized
+    {
+        self.and_then(f)
+    }
+
+    /// Combines this expression with another, yielding both values as a tuple.
+    ///
+    /// Unlike the `ops` operators, `zip` doesn't require both expressions to
+    /// share a type, so it's the way to combine two heterogeneous
+    /// expressions without an arithmetic operator.
+    fn zip<U, E2: Expression<U>>(self, other: E2) -> Lazy<(T, U), LazyZip<T, Self, U, E2>>
+        where Self: Sized
+    {
+        Lazy::new(LazyZip(self, other, PhantomData, PhantomData))
+    }
+
+    /// Collapses a nested expression into a single one.
+    ///
+    /// For an expression which evaluates to another expression, `flatten`
+    /// evaluates the outer expression and then the inner one it produced.
+    fn flatten<U>(self) -> Lazy<U, LazyFlatten<T, Self, U>>
+        where Self: Sized, T: Expression<U>
+    {
+        Lazy::new(LazyFlatten(self, PhantomData, PhantomData))
+    }
 }
 
 /// Internal type returned by `Expression::<T>::map`.
@@ -97,6 +288,92 @@ impl<T, E: Expression<T>, U, F: Fn(T) -> U> Expression<U> for LazyMap<T, E, U, F
         let LazyMap(expr, f, _, _) = self;
         f(expr.evaluate())
     }
+
+    fn try_evaluate(self) -> Result<U, EvalError> {
+        let LazyMap(expr, f, _, _) = self;
+        Ok(f(expr.try_evaluate()?))
+    }
+
+    fn describe(&self) -> ExprNode {
+        ExprNode::Map(Box::new(self.0.describe()))
+    }
+
+    fn can_fold(&self) -> bool {
+        self.0.can_fold()
+    }
+
+    fn fold(self) -> Folded<U, Self> {
+        let LazyMap(expr, f, _, _) = self;
+        match expr.fold() {
+            Folded::Const(value) => Folded::Const(f(value)),
+            Folded::Dynamic(expr) => Folded::Dynamic(LazyMap(expr, f, PhantomData, PhantomData)),
+        }
+    }
+}
+
+/// Internal type returned by `Expression::<T>::and_then` and `flat_map`.
+///
+/// See their documentation for details.
+pub struct LazyAndThen<T, E: Expression<T>, U, E2: Expression<U>, F: FnOnce(T) -> E2>(E, F, PhantomData<T>, PhantomData<U>, PhantomData<E2>);
+
+impl<T, E: Expression<T>, U, E2: Expression<U>, F: FnOnce(T) -> E2> Expression<U> for LazyAndThen<T, E, U, E2, F> {
+    fn evaluate(self) -> U {
+        let LazyAndThen(expr, f, _, _, _) = self;
+        f(expr.evaluate()).evaluate()
+    }
+
+    fn try_evaluate(self) -> Result<U, EvalError> {
+        let LazyAndThen(expr, f, _, _, _) = self;
+        f(expr.try_evaluate()?).try_evaluate()
+    }
+
+    fn describe(&self) -> ExprNode {
+        ExprNode::AndThen(Box::new(self.0.describe()))
+    }
+}
+
+/// Internal type returned by `Expression::<T>::zip`.
+///
+/// See its documentation for details.
+pub struct LazyZip<T, E: Expression<T>, U, E2: Expression<U>>(E, E2, PhantomData<T>, PhantomData<U>);
+
+impl<T, E: Expression<T>, U, E2: Expression<U>> Expression<(T, U)> for LazyZip<T, E, U, E2> {
+    fn evaluate(self) -> (T, U) {
+        let LazyZip(lhs, rhs, _, _) = self;
+        (lhs.evaluate(), rhs.evaluate())
+    }
+
+    fn try_evaluate(self) -> Result<(T, U), EvalError> {
+        let LazyZip(lhs, rhs, _, _) = self;
+        let lhs = lhs.try_evaluate()?;
+        let rhs = rhs.try_evaluate()?;
+        Ok((lhs, rhs))
+    }
+
+    fn describe(&self) -> ExprNode {
+        ExprNode::Zip(Box::new(self.0.describe()), Box::new(self.1.describe()))
+    }
+}
+
+/// Internal type returned by `Expression::<T>::flatten`.
+///
+/// See its documentation for details.
+pub struct LazyFlatten<T: Expression<U>, E: Expression<T>, U>(E, PhantomData<T>, PhantomData<U>);
+
+impl<T: Expression<U>, E: Expression<T>, U> Expression<U> for LazyFlatten<T, E, U> {
+    fn evaluate(self) -> U {
+        let LazyFlatten(expr, _, _) = self;
+        expr.evaluate().evaluate()
+    }
+
+    fn try_evaluate(self) -> Result<U, EvalError> {
+        let LazyFlatten(expr, _, _) = self;
+        expr.try_evaluate()?.try_evaluate()
+    }
+
+    fn describe(&self) -> ExprNode {
+        ExprNode::Flatten(Box::new(self.0.describe()))
+    }
 }
 
 #[cfg(test)]
@@ -120,4 +397,79 @@ mod tests {
         let a = lazy(2i32).map(|n| n.pow(3));
         assert_eq!(a.evaluate(), 8);
     }
+
+    #[test]
+    fn and_then() {
+        let (count, mut set_count) = param::Parameter::<i32>::empty();
+        let a = lazy(3i32).and_then(|n| count.map(move |c| n * c));
+        set_count.set(4);
+        assert_eq!(a.evaluate(), 12);
+    }
+
+    #[test]
+    fn and_then_try_evaluate_propagates_missing_parameter() {
+        let (count, _) = param::Parameter::<i32>::empty();
+        let a = lazy(3i32).and_then(|n| count.map(move |c| n * c));
+        assert_eq!(a.try_evaluate(), Err(EvalError::MissingParameter));
+    }
+
+    #[test]
+    fn flat_map_is_and_then() {
+        let a = lazy(3i32).flat_map(|n| lazy(n + 1));
+        assert_eq!(a.evaluate(), 4);
+    }
+
+    #[test]
+    fn zip() {
+        let a = lazy(3i32).zip(lazy("three"));
+        assert_eq!(a.evaluate(), (3, "three"));
+    }
+
+    #[test]
+    fn zip_try_evaluate_propagates_missing_parameter() {
+        let (count, _) = param::Parameter::<i32>::empty();
+        let a = count.zip(lazy("three"));
+        assert_eq!(a.try_evaluate(), Err(EvalError::MissingParameter));
+    }
+
+    #[test]
+    fn flatten() {
+        let a = lazy(lazy(5i32)).flatten();
+        assert_eq!(a.evaluate(), 5);
+    }
+
+    #[test]
+    fn flatten_try_evaluate_propagates_missing_parameter() {
+        let (count, _) = param::Parameter::<i32>::empty();
+        let a = lazy(count).flatten();
+        assert_eq!(a.try_evaluate(), Err(EvalError::MissingParameter));
+    }
+
+    #[test]
+    fn fold_constant_map() {
+        let a = lazy(2i32).map(|n| n.pow(3));
+        match a.fold() {
+            Folded::Const(value) => assert_eq!(value, 8),
+            Folded::Dynamic(_) => panic!("expected a fully constant expression to fold"),
+        }
+    }
+
+    #[test]
+    fn fold_leaves_parameter_untouched() {
+        let (count, mut set_count) = param::Parameter::<i32>::empty();
+        let a = count.map(|n| n.pow(3));
+        match a.fold() {
+            Folded::Const(_) => panic!("folding must not evaluate a Parameter"),
+            Folded::Dynamic(expr) => {
+                set_count.set(2);
+                assert_eq!(expr.evaluate(), 8);
+            }
+        }
+    }
+
+    #[test]
+    fn optimize_is_still_evaluable() {
+        let a = lazy(2i32).map(|n| n.pow(3)).optimize();
+        assert_eq!(a.evaluate(), 8);
+    }
 }
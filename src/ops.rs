@@ -0,0 +1,182 @@
+//! Arithmetic operator overloads for expressions.
+//!
+//! Lets two `Lazy` expressions of the same type be combined directly with
+//! `+`, `-`, `*` and `/`, instead of having to `map` them together by hand.
+
+use std::marker::PhantomData;
+use std::ops::{Add, Div, Mul, Sub};
+
+use super::{EvalError, Expression, Lazy};
+use super::describe::{ExprNode, OpKind};
+use super::fold::Folded;
+
+/// Marker for a binary numeric operation.
+///
+/// Lets `LazyBinOp` dispatch to the right `std::ops` trait without needing a
+/// distinct wrapper type per operator.
+pub trait BinOp<T> {
+    fn apply(lhs: T, rhs: T) -> T;
+    fn kind() -> OpKind;
+}
+
+macro_rules! bin_op {
+    ($marker:ident, $std_trait:ident, $method:ident, $kind:ident) => {
+        /// Marker type identifying the operator used by a `LazyBinOp`.
+        #[derive(Debug, Clone, Copy)]
+        pub struct $marker;
+
+        impl<T: $std_trait<Output = T>> BinOp<T> for $marker {
+            fn apply(lhs: T, rhs: T) -> T {
+                lhs.$method(rhs)
+            }
+
+            fn kind() -> OpKind {
+                OpKind::$kind
+            }
+        }
+    };
+}
+
+bin_op!(AddOp, Add, add, Add);
+bin_op!(SubOp, Sub, sub, Sub);
+bin_op!(MulOp, Mul, mul, Mul);
+bin_op!(DivOp, Div, div, Div);
+
+/// Internal type returned by the `+`, `-`, `*` and `/` operators on `Lazy`.
+///
+/// See the operator trait implementations on `Lazy` for details.
+#[derive(Debug, Clone)]
+pub struct LazyBinOp<T, L: Expression<T>, R: Expression<T>, O: BinOp<T>>(L, R, PhantomData<T>, PhantomData<O>);
+
+impl<T, L: Expression<T>, R: Expression<T>, O: BinOp<T>> Expression<T> for LazyBinOp<T, L, R, O> {
+    fn evaluate(self) -> T {
+        let LazyBinOp(lhs, rhs, _, _) = self;
+        O::apply(lhs.evaluate(), rhs.evaluate())
+    }
+
+    fn try_evaluate(self) -> Result<T, EvalError> {
+        let LazyBinOp(lhs, rhs, _, _) = self;
+        let lhs = lhs.try_evaluate()?;
+        let rhs = rhs.try_evaluate()?;
+        Ok(O::apply(lhs, rhs))
+    }
+
+    fn describe(&self) -> ExprNode {
+        ExprNode::BinOp {
+            op: O::kind(),
+            lhs: Box::new(self.0.describe()),
+            rhs: Box::new(self.1.describe()),
+        }
+    }
+
+    fn can_fold(&self) -> bool {
+        self.0.can_fold() && self.1.can_fold()
+    }
+
+    fn fold(self) -> Folded<T, Self> {
+        let LazyBinOp(lhs, rhs, _, _) = self;
+
+        // Only consume the operands through `fold` once we already know,
+        // from the non-consuming `can_fold`, that both will come back
+        // `Const` - otherwise we'd have no way to rebuild a `LazyBinOp<T, L,
+        // R, O>` from a mix of a plain value and an untouched operand.
+        if lhs.can_fold() && rhs.can_fold() {
+            let lhs = match lhs.fold() {
+                Folded::Const(value) => value,
+                Folded::Dynamic(_) => unreachable!("can_fold reported foldable but fold was dynamic"),
+            };
+            let rhs = match rhs.fold() {
+                Folded::Const(value) => value,
+                Folded::Dynamic(_) => unreachable!("can_fold reported foldable but fold was dynamic"),
+            };
+            Folded::Const(O::apply(lhs, rhs))
+        } else {
+            Folded::Dynamic(LazyBinOp(lhs, rhs, PhantomData, PhantomData))
+        }
+    }
+}
+
+macro_rules! impl_operator {
+    ($std_trait:ident, $method:ident, $marker:ident) => {
+        impl<T, L: Expression<T>, R: Expression<T>> $std_trait<Lazy<T, R>> for Lazy<T, L>
+            where $marker: BinOp<T>
+        {
+            type Output = Lazy<T, LazyBinOp<T, L, R, $marker>>;
+
+            fn $method(self, rhs: Lazy<T, R>) -> Self::Output {
+                let Lazy(lhs, _) = self;
+                let Lazy(rhs, _) = rhs;
+                Lazy::new(LazyBinOp(lhs, rhs, PhantomData, PhantomData))
+            }
+        }
+    };
+}
+
+impl_operator!(Add, add, AddOp);
+impl_operator!(Sub, sub, SubOp);
+impl_operator!(Mul, mul, MulOp);
+impl_operator!(Div, div, DivOp);
+
+#[cfg(test)]
+mod tests {
+    use super::super::prelude::*;
+
+    #[test]
+    fn add() {
+        let a = lazy(2) + lazy(3);
+        assert_eq!(a.evaluate(), 5);
+    }
+
+    #[test]
+    fn sub() {
+        let a = lazy(5) - lazy(3);
+        assert_eq!(a.evaluate(), 2);
+    }
+
+    #[test]
+    fn mul() {
+        let a = lazy(2) * lazy(3);
+        assert_eq!(a.evaluate(), 6);
+    }
+
+    #[test]
+    fn div() {
+        let a = lazy(6) / lazy(3);
+        assert_eq!(a.evaluate(), 2);
+    }
+
+    #[test]
+    fn try_evaluate_propagates_missing_parameter() {
+        let (x, _) = Parameter::<i32>::empty();
+        let a = lazy(1) + x.map(|n| n + 1);
+        assert_eq!(a.try_evaluate(), Err(EvalError::MissingParameter));
+    }
+
+    #[test]
+    fn describe_bin_op() {
+        let a = lazy(2) + lazy(3);
+        assert_eq!(a.describe().to_string(), "(<value> + <value>)");
+    }
+
+    #[test]
+    fn fold_constant_bin_op() {
+        let a = lazy(2) + lazy(3);
+        match a.fold() {
+            Folded::Const(value) => assert_eq!(value, 5),
+            Folded::Dynamic(_) => panic!("expected a fully constant expression to fold"),
+        }
+    }
+
+    #[test]
+    fn fold_leaves_bin_op_with_parameter_untouched() {
+        let (x, mut xs) = Parameter::<i32>::empty();
+        let a = lazy(2) + Lazy::new(x);
+        match a.fold() {
+            Folded::Const(_) => panic!("folding must not evaluate a Parameter"),
+            Folded::Dynamic(expr) => {
+                xs.set(3);
+                assert_eq!(expr.evaluate(), 5);
+            }
+        }
+    }
+}
@@ -3,7 +3,8 @@
 use std::cell::Cell;
 use std::rc::Rc;
 
-use super::Expression;
+use super::{EvalError, Expression};
+use super::describe::ExprNode;
 
 /// A handle to assign the parameter's value.
 ///
@@ -54,8 +55,22 @@ impl<T> Expression<T> for Parameter<T> {
     ///
     /// # Panics
     /// When evaluated without an undefined value.
-    fn evaluate(mut self) -> T {
-        self.take().expect("Parameter value not provided")
+    fn evaluate(self) -> T {
+        self.try_evaluate().expect("Parameter value not provided")
+    }
+
+    /// Yields the value of the parameter, or `Err` if none was provided.
+    fn try_evaluate(mut self) -> Result<T, EvalError> {
+        self.take().ok_or(EvalError::MissingParameter)
+    }
+
+    fn describe(&self) -> ExprNode {
+        // Peek without consuming: swap the value out and straight back in.
+        let value = self.0.replace(None);
+        let filled = value.is_some();
+        self.0.replace(value);
+
+        ExprNode::Parameter { filled }
     }
 }
 
@@ -93,4 +108,29 @@ mod tests {
         let expr = param.map(|n| n.pow(3));
         expr.evaluate();
     }
+
+    #[test]
+    fn try_evaluate_missing_value() {
+        let (param, _) = Parameter::<u32>::empty();
+        let expr = param.map(|n| n.pow(3));
+        assert_eq!(expr.try_evaluate(), Err(EvalError::MissingParameter));
+    }
+
+    #[test]
+    fn try_evaluate_provided_value() {
+        let (param, mut setter) = Parameter::<u32>::empty();
+        let expr = param.map(|n| n.pow(3));
+        setter.set(10u32);
+        assert_eq!(expr.try_evaluate(), Ok(1000));
+    }
+
+    #[test]
+    fn describe_reports_fill_state_without_consuming() {
+        let (param, mut setter) = Parameter::<u32>::empty();
+        assert_eq!(param.describe(), ExprNode::Parameter { filled: false });
+
+        setter.set(10u32);
+        assert_eq!(param.describe(), ExprNode::Parameter { filled: true });
+        assert_eq!(param.evaluate(), 10);
+    }
 }
@@ -0,0 +1,159 @@
+//! Thread-safe parameters, for expressions that cross thread boundaries.
+//!
+//! [`Parameter`](super::param::Parameter) is built on `Rc<Cell<...>>`, which
+//! is neither `Send` nor `Sync`, so it can't be handed to another thread or
+//! stored in a `static`. [`SyncParameter`] is the `Arc`/`Mutex`-backed
+//! equivalent: a builder thread can construct an expression out of
+//! `SyncParameter`s (composed with `map` and the `ops` operators, same as
+//! any other expression), hand the whole thing to a worker thread, and fill
+//! in the values from yet another thread before it's evaluated.
+//!
+//! The guarantee that matters here is ordering: a [`SyncParameterContent::set`]
+//! happens-before any [`SyncParameter::evaluate`] or
+//! [`SyncParameter::try_evaluate`] that observes the set value, because both
+//! go through the same `Mutex`.
+
+use std::sync::{Arc, Mutex};
+
+use super::{EvalError, Expression};
+use super::describe::ExprNode;
+
+/// A handle to assign a `SyncParameter`'s value from any thread.
+#[derive(Clone)]
+pub struct SyncParameterContent<T>(Arc<Mutex<Option<T>>>);
+
+impl<T> SyncParameterContent<T> {
+    pub fn set(&mut self, value: T) {
+        *self.0.lock().unwrap() = Some(value);
+    }
+}
+
+/// Thread-safe counterpart to [`Parameter`](super::param::Parameter).
+///
+/// A value that is unknown at the time of the expression's definition, but
+/// will be known, possibly set from another thread, before it is evaluated.
+#[derive(Clone)]
+pub struct SyncParameter<T>(Arc<Mutex<Option<T>>>);
+
+impl<T> SyncParameter<T> {
+    fn create_with(value: Option<T>) -> (Self, SyncParameterContent<T>) {
+        let inner = Arc::new(Mutex::new(value));
+        (SyncParameter(inner.clone()), SyncParameterContent(inner))
+    }
+
+    /// Creates a parameter with no initial value.
+    pub fn empty() -> (Self, SyncParameterContent<T>) {
+        Self::create_with(None)
+    }
+
+    /// Creates a parameter with an initial value.
+    ///
+    /// You can still change its value through the returned `Content` handle.
+    pub fn new(value: T) -> (Self, SyncParameterContent<T>) {
+        Self::create_with(Some(value))
+    }
+
+    fn take(&mut self) -> Option<T> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+impl<T> Expression<T> for SyncParameter<T> {
+    /// Yields the value of the parameter.
+    ///
+    /// # Panics
+    /// When evaluated without a defined value.
+    fn evaluate(self) -> T {
+        self.try_evaluate().expect("Parameter value not provided")
+    }
+
+    /// Yields the value of the parameter, or `Err` if none was provided.
+    fn try_evaluate(mut self) -> Result<T, EvalError> {
+        self.take().ok_or(EvalError::MissingParameter)
+    }
+
+    fn describe(&self) -> ExprNode {
+        let filled = self.0.lock().unwrap().is_some();
+        ExprNode::Parameter { filled }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn correct_usage_empty() {
+        let (param, mut setter) = SyncParameter::<u32>::empty();
+        let expr = param.map(|n| n.pow(3));
+        setter.set(10u32);
+        assert_eq!(expr.evaluate(), 1000);
+    }
+
+    #[test]
+    fn correct_usage_prefilled() {
+        let (param, _) = SyncParameter::new(10u32);
+        let expr = param.map(|n| n.pow(3));
+        assert_eq!(expr.evaluate(), 1000);
+    }
+
+    #[test]
+    fn correct_usage_override() {
+        let (param, mut setter) = SyncParameter::new(10u32);
+        let expr = param.map(|n| n.pow(3));
+        setter.set(2u32);
+        assert_eq!(expr.evaluate(), 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn incorrect_usage() {
+        let (param, _) = SyncParameter::<u32>::empty();
+        let expr = param.map(|n| n.pow(3));
+        expr.evaluate();
+    }
+
+    #[test]
+    fn try_evaluate_missing_value() {
+        let (param, _) = SyncParameter::<u32>::empty();
+        let expr = param.map(|n| n.pow(3));
+        assert_eq!(expr.try_evaluate(), Err(EvalError::MissingParameter));
+    }
+
+    #[test]
+    fn try_evaluate_provided_value() {
+        let (param, mut setter) = SyncParameter::<u32>::empty();
+        let expr = param.map(|n| n.pow(3));
+        setter.set(10u32);
+        assert_eq!(expr.try_evaluate(), Ok(1000));
+    }
+
+    #[test]
+    fn describe_reports_fill_state_without_consuming() {
+        let (param, mut setter) = SyncParameter::<u32>::empty();
+        assert_eq!(param.describe(), ExprNode::Parameter { filled: false });
+
+        setter.set(10u32);
+        assert_eq!(param.describe(), ExprNode::Parameter { filled: true });
+        assert_eq!(param.evaluate(), 10);
+    }
+
+    #[test]
+    fn filled_from_another_thread() {
+        let (x, mut xs) = SyncParameter::<f32>::empty();
+        let (y, mut ys) = SyncParameter::<f32>::empty();
+        let magnitude = (x.map(|n| n.powf(2.0)) + y.map(|n| n.powf(2.0))).map(f32::sqrt);
+        assert_send_sync(&magnitude);
+
+        let setter = thread::spawn(move || {
+            xs.set(5.0);
+            ys.set(12.0);
+        });
+        setter.join().unwrap();
+
+        assert_eq!(magnitude.evaluate(), 13.0);
+    }
+}